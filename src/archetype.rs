@@ -0,0 +1,286 @@
+
+//! Archetype-based component storage.
+//!
+//! `ComponentList` stores one `Buffer` per component type and tests entity membership
+//! with a `Bitv`, which costs a membership check per component per entity when a
+//! `System`'s `Aspect` requires several of them. Archetype storage instead groups
+//! entities by their exact component set, so a multi-component `Aspect` can be
+//! satisfied by a tight linear scan of packed parallel arrays. It's an alternative
+//! iteration path that sits alongside `ComponentList`, not a replacement for it.
+
+use std::collections::HashMap;
+
+use buffer::Buffer;
+use component::{ComponentId, ComponentList};
+use Entity;
+
+/// Identifies an `Archetype` within an `Archetypes` registry.
+pub type ArchetypeId = uint;
+
+/// A group of entities that all have exactly the same set of components, stored as
+/// tightly packed parallel arrays, one `Buffer` per component type.
+pub struct Archetype
+{
+    mask: Vec<ComponentId>,
+    buffers: HashMap<ComponentId, Buffer>,
+    entities: Vec<Entity>,
+}
+
+impl Archetype
+{
+    fn new(mask: Vec<ComponentId>) -> Archetype
+    {
+        Archetype
+        {
+            mask: mask,
+            buffers: HashMap::new(),
+            entities: Vec::new(),
+        }
+    }
+
+    /// Returns true if this archetype carries every component in `required`.
+    pub fn matches(&self, required: &[ComponentId]) -> bool
+    {
+        required.iter().all(|id| self.mask.contains(id))
+    }
+
+    /// The entities stored in this archetype, in row order.
+    pub fn entities(&self) -> &[Entity]
+    {
+        self.entities.as_slice()
+    }
+
+    /// Returns the raw bytes of the component `id` at `row`, if this archetype carries `id`.
+    pub fn get_raw(&self, id: ComponentId, row: uint) -> Option<&[u8]>
+    {
+        self.buffers.get(&id).and_then(|buffer| buffer.get_raw(row))
+    }
+
+    /// Mutably borrows the raw bytes of the component `id` at `row`.
+    pub fn get_raw_mut(&mut self, id: ComponentId, row: uint) -> Option<&mut [u8]>
+    {
+        self.buffers.get_mut(&id).map(|buffer| buffer.get_raw_mut(row))
+    }
+
+    /// Appends a row for `source`, pulling every mask component's current bytes straight
+    /// from `components` (the authoritative `ComponentList` storage). Returns the new
+    /// row index.
+    ///
+    /// Always re-reading from `components`, rather than carrying bytes forward from
+    /// wherever the entity's row previously lived, means a row is never left zero-filled
+    /// for a component the entity already has just because that component didn't happen
+    /// to live in the entity's last archetype.
+    fn push(&mut self, entity: Entity, source: &Entity, components: &HashMap<ComponentId, ComponentList>, strides: &HashMap<ComponentId, uint>) -> uint
+    {
+        let row = self.entities.len();
+        self.entities.push(entity);
+        for id in self.mask.clone().iter()
+        {
+            if !self.buffers.contains_key(id)
+            {
+                let stride = *strides.get(id).unwrap_or(&0u);
+                self.buffers.insert(*id, Buffer::with_stride(stride));
+            }
+            let bytes = components.get(id).and_then(|list| list.get_raw(source)).map(|b| b.to_vec());
+            if let Some(bytes) = bytes
+            {
+                let slot = self.buffers.get_mut(id).unwrap().get_raw_mut(row);
+                for (d, s) in slot.iter_mut().zip(bytes.iter())
+                {
+                    *d = *s;
+                }
+            }
+        }
+        row
+    }
+
+    /// Swap-removes `row`, keeping the buffers dense. Returns the entity that now
+    /// occupies `row` in place of the removed one, if any.
+    fn swap_remove(&mut self, row: uint) -> Option<Entity>
+    {
+        for buffer in self.buffers.values_mut()
+        {
+            buffer.swap_remove_raw(row);
+        }
+        self.entities.swap_remove(row);
+        if row < self.entities.len() { Some(self.entities[row].clone()) } else { None }
+    }
+}
+
+/// Tracks every `Archetype` and which `(archetype, row)` each entity currently lives at.
+pub struct Archetypes
+{
+    strides: HashMap<ComponentId, uint>,
+    archetypes: Vec<Archetype>,
+    by_mask: HashMap<Vec<ComponentId>, ArchetypeId>,
+    locations: HashMap<uint, (ArchetypeId, uint)>,
+}
+
+impl Archetypes
+{
+    /// Returns a new, empty `Archetypes` registry.
+    pub fn new() -> Archetypes
+    {
+        Archetypes
+        {
+            strides: HashMap::new(),
+            archetypes: Vec::new(),
+            by_mask: HashMap::new(),
+            locations: HashMap::new(),
+        }
+    }
+
+    /// Registers the byte stride of a component type, so archetypes that carry it know
+    /// how large each row's slot is.
+    pub fn register_component(&mut self, id: ComponentId, stride: uint)
+    {
+        self.strides.insert(id, stride);
+    }
+
+    /// Returns the `(archetype, row)` an entity currently lives at, if any.
+    pub fn location(&self, entity: &Entity) -> Option<(ArchetypeId, uint)>
+    {
+        self.locations.get(&**entity).map(|loc| *loc)
+    }
+
+    fn archetype_for(&mut self, mut mask: Vec<ComponentId>) -> ArchetypeId
+    {
+        mask.sort();
+        mask.dedup();
+        match self.by_mask.get(&mask)
+        {
+            Some(id) => return *id,
+            None => {}
+        }
+        let id = self.archetypes.len();
+        self.archetypes.push(Archetype::new(mask.clone()));
+        self.by_mask.insert(mask, id);
+        id
+    }
+
+    /// Moves `entity` into the archetype matching its new full component set, rebuilding
+    /// its row from scratch with every mask component's current bytes read straight out
+    /// of `components`. Call this whenever a component is added to or removed from an
+    /// entity.
+    pub fn set_components(&mut self, entity: &Entity, mask: Vec<ComponentId>, components: &HashMap<ComponentId, ComponentList>) -> ArchetypeId
+    {
+        if let Some((source, row)) = self.locations.remove(&**entity)
+        {
+            self.remove_row(source, row);
+        }
+
+        let target = self.archetype_for(mask);
+        let row = self.archetypes[mut][target].push(entity.clone(), entity, components, &self.strides);
+        self.locations.insert(**entity, (target, row));
+        target
+    }
+
+    /// Overwrites the raw bytes of `entity`'s current row for component `id`, if it's in
+    /// archetype storage and its archetype carries `id`.
+    ///
+    /// Used to push a freshly written value in immediately, without waiting for the next
+    /// `set_components` call to rebuild the whole row.
+    pub fn sync_row(&mut self, entity: &Entity, id: ComponentId, bytes: &[u8])
+    {
+        if let Some((archetype, row)) = self.location(entity)
+        {
+            if let Some(slot) = self.archetypes[mut][archetype].get_raw_mut(id, row)
+            {
+                for (d, s) in slot.iter_mut().zip(bytes.iter())
+                {
+                    *d = *s;
+                }
+            }
+        }
+    }
+
+    /// Removes `entity` from archetype storage entirely.
+    ///
+    /// Must be called when an entity is deleted from the world: `IdPool` recycles its
+    /// index, and a stale `(archetype, row)` entry left behind would otherwise make the
+    /// next entity created with that index appear to carry the dead entity's data.
+    pub fn remove_entity(&mut self, entity: &Entity)
+    {
+        if let Some((archetype, row)) = self.locations.remove(&**entity)
+        {
+            self.remove_row(archetype, row);
+        }
+    }
+
+    /// Swap-removes `row` from `archetype`, fixing up the location of whichever entity
+    /// was moved into its place.
+    fn remove_row(&mut self, archetype: ArchetypeId, row: uint)
+    {
+        let moved = self.archetypes[mut][archetype].swap_remove(row);
+        if let Some(moved_entity) = moved
+        {
+            self.locations.insert(*moved_entity, (archetype, row));
+        }
+    }
+
+    /// Returns every archetype whose component set is a superset of `required`, for a
+    /// `System` to walk directly instead of testing every entity against its `Aspect`.
+    pub fn matching(&self, required: &[ComponentId]) -> Vec<&Archetype>
+    {
+        self.archetypes.iter().filter(|a| a.matches(required)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::collections::HashMap;
+
+    use super::Archetypes;
+    use component::{ComponentId, ComponentList};
+    use uuid::Uuid;
+    use Entity;
+
+    const ID: ComponentId = 1u64;
+
+    fn put(archetypes: &mut Archetypes, entity: &Entity, byte: u8)
+    {
+        let components: HashMap<ComponentId, ComponentList> = HashMap::new();
+        archetypes.set_components(entity, vec![ID], &components);
+        archetypes.sync_row(entity, ID, &[byte]);
+    }
+
+    #[test]
+    fn swap_remove_reindexes_the_moved_entity()
+    {
+        let mut archetypes = Archetypes::new();
+        archetypes.register_component(ID, 1);
+
+        let a = Entity::new(0, Uuid::new_v4());
+        let b = Entity::new(1, Uuid::new_v4());
+        let c = Entity::new(2, Uuid::new_v4());
+        put(&mut archetypes, &a, 10);
+        put(&mut archetypes, &b, 20);
+        put(&mut archetypes, &c, 30);
+
+        // Removing `a` (row 0) swaps the last row (`c`'s) into its place.
+        archetypes.remove_entity(&a);
+
+        let (_, row) = archetypes.location(&c).unwrap();
+        assert_eq!(row, 0u);
+        // Only one archetype exists (every entity here shares the same `[ID]` mask).
+        assert_eq!(archetypes.matching(&[ID])[0].get_raw(ID, row).unwrap(), [30u8].as_slice());
+
+        // `b` never moved.
+        let (_, b_row) = archetypes.location(&b).unwrap();
+        assert_eq!(b_row, 1u);
+    }
+
+    #[test]
+    fn removed_entity_is_not_findable_afterwards()
+    {
+        let mut archetypes = Archetypes::new();
+        archetypes.register_component(ID, 1);
+
+        let entity = Entity::new(0, Uuid::new_v4());
+        put(&mut archetypes, &entity, 42);
+        archetypes.remove_entity(&entity);
+
+        assert!(archetypes.location(&entity).is_none());
+    }
+}
@@ -0,0 +1,144 @@
+
+//! Helper to filter entities based on their components.
+
+use component::{Component, ComponentId};
+use world::Components;
+use Entity;
+
+/// Filters entities based on the components they have.
+///
+/// An `Aspect` is built with one of `for_all`, `for_one`, or `for_none`, and is checked
+/// against an entity's current components with `check`.
+#[deriving(Clone)]
+pub struct Aspect
+{
+    all: Vec<ComponentId>,
+    one: Vec<ComponentId>,
+    none: Vec<ComponentId>,
+    added: Vec<ComponentId>,
+    changed: Vec<ComponentId>,
+    never: bool,
+}
+
+impl Aspect
+{
+    /// Returns an `Aspect` that matches every entity.
+    pub fn nil() -> Aspect
+    {
+        Aspect
+        {
+            all: Vec::new(),
+            one: Vec::new(),
+            none: Vec::new(),
+            added: Vec::new(),
+            changed: Vec::new(),
+            never: false,
+        }
+    }
+
+    /// Returns an `Aspect` that matches no entities.
+    ///
+    /// Distinct from `for_none(Vec::new())`, which matches *every* entity: an empty
+    /// `none` list is vacuously satisfied by anything, so there's no way to express
+    /// "nothing" through the `all`/`one`/`none` filters alone.
+    pub fn never() -> Aspect
+    {
+        let mut aspect = Aspect::nil();
+        aspect.never = true;
+        aspect
+    }
+
+    /// Returns an `Aspect` that matches entities having all of `ids`.
+    pub fn for_all(ids: Vec<ComponentId>) -> Aspect
+    {
+        let mut aspect = Aspect::nil();
+        aspect.all = ids;
+        aspect
+    }
+
+    /// Returns an `Aspect` that matches entities having at least one of `ids`.
+    pub fn for_one(ids: Vec<ComponentId>) -> Aspect
+    {
+        let mut aspect = Aspect::nil();
+        aspect.one = ids;
+        aspect
+    }
+
+    /// Returns an `Aspect` that matches entities having none of `ids`.
+    pub fn for_none(ids: Vec<ComponentId>) -> Aspect
+    {
+        let mut aspect = Aspect::nil();
+        aspect.none = ids;
+        aspect
+    }
+
+    /// Narrows this aspect to entities whose `T` component was added since a system's
+    /// last run. Intended for use by `System::aspect` together with `Components::is_added`.
+    pub fn added<T:Component>(mut self) -> Aspect
+    {
+        self.added.push(component_id!(T));
+        self
+    }
+
+    /// Narrows this aspect to entities whose `T` component was added or mutated since a
+    /// system's last run. Intended for use by `System::aspect`.
+    pub fn changed<T:Component>(mut self) -> Aspect
+    {
+        self.changed.push(component_id!(T));
+        self
+    }
+
+    /// Returns true if `entity` satisfies this aspect's `all`/`one`/`none` filters.
+    pub fn check(&self, entity: &Entity, components: &Components) -> bool
+    {
+        !self.never
+        && self.all.iter().all(|id| components.has(*id, entity))
+        && (self.one.len() == 0 || self.one.iter().any(|id| components.has(*id, entity)))
+        && self.none.iter().all(|id| !components.has(*id, entity))
+    }
+
+    /// The component ids this aspect requires every matching entity to have.
+    ///
+    /// Used to resolve this aspect to a set of archetypes (see `archetype::Archetypes::matching`)
+    /// rather than testing every entity individually.
+    pub fn required_ids(&self) -> &[ComponentId]
+    {
+        self.all.as_slice()
+    }
+
+    /// Like `check`, but also requires any `added`/`changed` filters to match components
+    /// touched since `last_run`, so a `System` only sees the entities it cares about.
+    pub fn check_since(&self, entity: &Entity, components: &Components, last_run: u32) -> bool
+    {
+        self.check(entity, components)
+        && self.added.iter().all(|id| components.is_added(*id, entity, last_run))
+        && self.changed.iter().all(|id| components.is_changed(*id, entity, last_run))
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::Aspect;
+    use world::{Components, WorldBuilder};
+
+    #[test]
+    fn never_matches_no_entities()
+    {
+        let mut world = WorldBuilder::new().build();
+        let entity = world.create_entity(());
+        let components = Components::new(&mut world);
+        assert!(!Aspect::never().check(&entity, &components));
+    }
+
+    #[test]
+    fn for_none_of_empty_set_matches_every_entity()
+    {
+        // Contrast with `never`: an empty `none` list can't express "nothing", since
+        // it's vacuously satisfied by anything.
+        let mut world = WorldBuilder::new().build();
+        let entity = world.create_entity(());
+        let components = Components::new(&mut world);
+        assert!(Aspect::for_none(Vec::new()).check(&entity, &components));
+    }
+}
@@ -5,6 +5,7 @@
 // This is a strange, wonderful, disgusting, and useful object.
 
 use std::mem;
+use std::ptr;
 use std::raw::Slice;
 use std::slice::AsSlice;
 use std::ops::{Index, IndexMut};
@@ -64,6 +65,18 @@ impl Buffer
         }
     }
 
+    /// Returns an empty buffer storing elements of the given byte `stride`, without
+    /// requiring a concrete Rust type. Used by untyped/archetype storage.
+    #[inline(always)]
+    pub fn with_stride(stride: uint) -> Buffer
+    {
+        Buffer
+        {
+            bytes: Vec::new(),
+            stride: stride,
+        }
+    }
+
     pub fn as_mut_slice<T:'static>(&mut self) -> &mut [T]
     {
         debug_assert_eq!(mem::size_of::<T>(), self.stride);
@@ -100,6 +113,88 @@ impl Buffer
     {
         &self.bytes
     }
+
+    /// Returns the raw bytes of the element at `index`, or `None` if out of bounds.
+    ///
+    /// Lets callers that only know a `stride`, not a concrete type, read an element.
+    pub fn get_raw(&self, index: uint) -> Option<&[u8]>
+    {
+        if index < self.len()
+        {
+            let offset = self.stride * index;
+            unsafe
+            {
+                Some(mem::transmute(Slice
+                {
+                    data: self.bytes.as_ptr().offset(offset as int),
+                    len: self.stride,
+                }))
+            }
+        }
+        else
+        {
+            None
+        }
+    }
+
+    /// Returns the raw, mutable bytes of the element at `index`, growing the buffer to
+    /// fit if necessary.
+    pub fn get_raw_mut(&mut self, index: uint) -> &mut [u8]
+    {
+        let stride = self.stride;
+        let offset = stride * index;
+        let length = self.bytes.len();
+        if offset + stride > length
+        {
+            self.bytes.grow(offset + stride - length, 0);
+        }
+        unsafe
+        {
+            mem::transmute(Slice
+            {
+                data: self.bytes.as_mut_ptr().offset(offset as int),
+                len: stride,
+            })
+        }
+    }
+
+    /// Overwrites the element at `index` with `bytes`, rejecting lengths that don't match `stride`.
+    pub fn set_raw(&mut self, index: uint, bytes: &[u8]) -> bool
+    {
+        if bytes.len() != self.stride
+        {
+            false
+        }
+        else
+        {
+            let dest = self.get_raw_mut(index);
+            for (d, s) in dest.iter_mut().zip(bytes.iter())
+            {
+                *d = *s;
+            }
+            true
+        }
+    }
+
+    /// Removes the element at `index`, moving the last element's bytes into its place
+    /// (if it wasn't already last), and shrinking the buffer by one element. Mirrors
+    /// `Vec::swap_remove`.
+    pub fn swap_remove_raw(&mut self, index: uint)
+    {
+        let stride = self.stride;
+        let last_index = self.len() - 1;
+        if index != last_index
+        {
+            unsafe
+            {
+                let src = self.bytes.as_ptr().offset((stride * last_index) as int);
+                let dst = self.bytes.as_mut_ptr().offset((stride * index) as int);
+                ptr::copy_nonoverlapping_memory(dst, src, stride);
+            }
+        }
+        let new_len = stride * last_index;
+        self.bytes.truncate(new_len);
+    }
 }
 
 pub trait IntoBuffer
@@ -122,4 +217,75 @@ impl<T> IntoBuffer for Vec<T> where T:'static
             }
         }
     }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::Buffer;
+
+    #[test]
+    fn get_raw_is_none_past_the_end()
+    {
+        let buffer = Buffer::with_stride(4);
+        assert!(buffer.get_raw(0).is_none());
+    }
+
+    #[test]
+    fn get_raw_mut_grows_the_buffer_to_fit_and_is_readable_through_get_raw()
+    {
+        let mut buffer = Buffer::with_stride(4);
+        {
+            let slot = buffer.get_raw_mut(2);
+            for (d, s) in slot.iter_mut().zip([1u8, 2, 3, 4].iter())
+            {
+                *d = *s;
+            }
+        }
+        assert_eq!(buffer.get_raw(2).unwrap(), [1u8, 2, 3, 4].as_slice());
+        assert_eq!(buffer.len(), 3u);
+    }
+
+    #[test]
+    fn set_raw_round_trips_through_get_raw()
+    {
+        let mut buffer = Buffer::with_stride(2);
+        assert!(buffer.set_raw(0, &[9u8, 8u8]));
+        assert_eq!(buffer.get_raw(0).unwrap(), [9u8, 8u8].as_slice());
+    }
+
+    #[test]
+    fn set_raw_rejects_a_length_that_does_not_match_the_stride()
+    {
+        let mut buffer = Buffer::with_stride(4);
+        assert!(!buffer.set_raw(0, &[1u8, 2u8]));
+    }
+
+    #[test]
+    fn swap_remove_raw_moves_the_last_element_into_the_removed_slot()
+    {
+        let mut buffer = Buffer::with_stride(1);
+        buffer.set_raw(0, &[10u8]);
+        buffer.set_raw(1, &[20u8]);
+        buffer.set_raw(2, &[30u8]);
+
+        buffer.swap_remove_raw(0);
+
+        assert_eq!(buffer.len(), 2u);
+        assert_eq!(buffer.get_raw(0).unwrap(), [30u8].as_slice());
+        assert_eq!(buffer.get_raw(1).unwrap(), [20u8].as_slice());
+    }
+
+    #[test]
+    fn swap_remove_raw_of_the_last_element_just_shrinks()
+    {
+        let mut buffer = Buffer::with_stride(1);
+        buffer.set_raw(0, &[10u8]);
+        buffer.set_raw(1, &[20u8]);
+
+        buffer.swap_remove_raw(1);
+
+        assert_eq!(buffer.len(), 1u);
+        assert_eq!(buffer.get_raw(0).unwrap(), [10u8].as_slice());
+    }
 }
\ No newline at end of file
@@ -20,9 +20,21 @@ pub struct ComponentList
 {
     buffer: Buffer,
     enabled: Bitv,
+    added_tick: Vec<u32>,
+    changed_tick: Vec<u32>,
     id: ComponentId,
 }
 
+/// Grows `ticks` with zeroes so it covers `index`, mirroring how `enabled` grows.
+fn grow_ticks(ticks: &mut Vec<u32>, index: uint)
+{
+    if index >= ticks.len()
+    {
+        let diff = index - ticks.len();
+        ticks.grow(diff+1, 0);
+    }
+}
+
 impl ComponentList
 {
     pub fn new<T:Component>() -> ComponentList
@@ -31,11 +43,13 @@ impl ComponentList
         {
             buffer: Buffer::new::<T>(),
             enabled: Bitv::new(),
+            added_tick: Vec::new(),
+            changed_tick: Vec::new(),
             id: TypeId::of::<T>().hash(),
         }
     }
 
-    pub fn add<T:Component>(&mut self, entity: &Entity, component: &T) -> bool
+    pub fn add<T:Component>(&mut self, entity: &Entity, component: &T, tick: u32) -> bool
     {
         if **entity < self.enabled.len() && self.enabled.get(**entity)
         {
@@ -54,11 +68,15 @@ impl ComponentList
                 self.enabled.grow(diff+1, false);
             }
             self.enabled.set(**entity, true);
+            grow_ticks(&mut self.added_tick, **entity);
+            grow_ticks(&mut self.changed_tick, **entity);
+            self.added_tick[mut][**entity] = tick;
+            self.changed_tick[mut][**entity] = tick;
             true
         }
     }
 
-    pub fn set<T:Component>(&mut self, entity: &Entity, component: &T) -> bool
+    pub fn set<T:Component>(&mut self, entity: &Entity, component: &T, tick: u32) -> bool
     {
         if **entity >= self.enabled.len() || !self.enabled.get(**entity)
         {
@@ -71,11 +89,13 @@ impl ComponentList
         else
         {
             self.buffer[**entity] = component;
+            grow_ticks(&mut self.changed_tick, **entity);
+            self.changed_tick[mut][**entity] = tick;
             true
         }
     }
 
-    pub fn add_or_set<T:Component>(&mut self, entity: &Entity, component: &T) -> bool
+    pub fn add_or_set<T:Component>(&mut self, entity: &Entity, component: &T, tick: u32) -> bool
     {
         if TypeId::of::<T>().hash() != self.id
         {
@@ -90,6 +110,10 @@ impl ComponentList
                 self.enabled.grow(diff+1, false);
             }
             self.enabled.set(**entity, true);
+            grow_ticks(&mut self.added_tick, **entity);
+            grow_ticks(&mut self.changed_tick, **entity);
+            self.added_tick[mut][**entity] = tick;
+            self.changed_tick[mut][**entity] = tick;
             true
         }
     }
@@ -111,6 +135,7 @@ impl ComponentList
         }
     }
 
+    /// Borrows the component without marking it as changed, unlike `borrow_mut`.
     pub fn borrow<T:Component>(&self, entity: &Entity) -> Option<&T>
     {
         if **entity < self.enabled.len() && self.enabled.get(**entity)
@@ -123,10 +148,13 @@ impl ComponentList
         }
     }
 
-    pub fn borrow_mut<T:Component>(&mut self, entity: &Entity) -> Option<&mut T>
+    /// Mutably borrows the component, marking it as changed at `tick`.
+    pub fn borrow_mut<T:Component>(&mut self, entity: &Entity, tick: u32) -> Option<&mut T>
     {
         if **entity < self.enabled.len() && self.enabled.get(**entity)
         {
+            grow_ticks(&mut self.changed_tick, **entity);
+            self.changed_tick[mut][**entity] = tick;
             Some(&mut self.buffer[**entity])
         }
         else
@@ -152,4 +180,180 @@ impl ComponentList
     {
         self.id
     }
+
+    /// The byte stride of this component type, as tracked by the underlying `Buffer`.
+    pub fn stride(&self) -> uint
+    {
+        self.buffer.stride()
+    }
+
+    /// Returns the raw bytes of `entity`'s component, without knowing its Rust type.
+    ///
+    /// Lets a scripting or modding layer read arbitrary components given only a
+    /// `ComponentId`, mirroring the untyped `get_by_id`/`insert_by_id` style of access.
+    pub fn get_raw(&self, entity: &Entity) -> Option<&[u8]>
+    {
+        if self.has(entity)
+        {
+            self.buffer.get_raw(**entity)
+        }
+        else
+        {
+            None
+        }
+    }
+
+    /// Mutably borrows the raw bytes of `entity`'s component, marking it as changed at `tick`.
+    pub fn get_raw_mut(&mut self, entity: &Entity, tick: u32) -> Option<&mut [u8]>
+    {
+        if self.has(entity)
+        {
+            grow_ticks(&mut self.changed_tick, **entity);
+            self.changed_tick[mut][**entity] = tick;
+            Some(self.buffer.get_raw_mut(**entity))
+        }
+        else
+        {
+            None
+        }
+    }
+
+    /// Overwrites `entity`'s component with raw bytes, rejecting slices whose length
+    /// doesn't match the component's `stride`.
+    pub fn set_raw(&mut self, entity: &Entity, bytes: &[u8], tick: u32) -> bool
+    {
+        if !self.has(entity)
+        {
+            false
+        }
+        else if self.buffer.set_raw(**entity, bytes)
+        {
+            grow_ticks(&mut self.changed_tick, **entity);
+            self.changed_tick[mut][**entity] = tick;
+            true
+        }
+        else
+        {
+            false
+        }
+    }
+
+    /// Returns true if the component was added to `entity` after `last_run`.
+    ///
+    /// Gated on `has(entity)`: `rm` doesn't clear the tick slot it leaves behind, so
+    /// without this an entity index recycled after removal could read its predecessor's
+    /// stale tick and look freshly added when it isn't.
+    pub fn is_added(&self, entity: &Entity, last_run: u32) -> bool
+    {
+        self.has(entity) && **entity < self.added_tick.len() && self.added_tick[**entity] > last_run
+    }
+
+    /// Returns true if the component on `entity` was added or mutated after `last_run`.
+    ///
+    /// Gated on `has(entity)` for the same reason as `is_added`.
+    pub fn is_changed(&self, entity: &Entity, last_run: u32) -> bool
+    {
+        self.has(entity) && **entity < self.changed_tick.len() && self.changed_tick[**entity] > last_run
+    }
+
+    /// Clamps any tick ahead of `current` down to it, so a wrapped-around tick counter
+    /// can't leave stale entries that look newer than they are.
+    pub fn clamp_ticks(&mut self, current: u32)
+    {
+        for tick in self.added_tick.iter_mut()
+        {
+            if *tick > current { *tick = current; }
+        }
+        for tick in self.changed_tick.iter_mut()
+        {
+            if *tick > current { *tick = current; }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::ComponentList;
+    use uuid::Uuid;
+    use Entity;
+
+    #[test]
+    fn is_added_ignores_stale_tick_left_by_recycled_index()
+    {
+        let mut list = ComponentList::new::<u32>();
+        let first = Entity::new(0, Uuid::new_v4());
+        list.add(&first, &1u32, 5);
+        list.rm(&first);
+
+        // Index 0 is reused before a fresh `add` ever overwrites `added_tick[0]`.
+        let second = Entity::new(0, Uuid::new_v4());
+        assert!(!list.is_added(&second, 0));
+        assert!(!list.is_changed(&second, 0));
+    }
+
+    #[test]
+    fn clamp_ticks_brings_wrapped_tick_back_in_range()
+    {
+        let mut list = ComponentList::new::<u32>();
+        let entity = Entity::new(0, Uuid::new_v4());
+        list.add(&entity, &1u32, 100);
+        list.clamp_ticks(10);
+        assert!(!list.is_added(&entity, 10));
+    }
+
+    #[test]
+    fn get_raw_returns_the_bytes_just_added()
+    {
+        let mut list = ComponentList::new::<u32>();
+        let entity = Entity::new(0, Uuid::new_v4());
+        list.add(&entity, &1u32, 0);
+        assert_eq!(list.get_raw(&entity).unwrap(), [1u8, 0, 0, 0].as_slice());
+    }
+
+    #[test]
+    fn get_raw_mut_writes_are_visible_through_get_raw()
+    {
+        let mut list = ComponentList::new::<u32>();
+        let entity = Entity::new(0, Uuid::new_v4());
+        list.add(&entity, &1u32, 0);
+
+        list.get_raw_mut(&entity, 1).unwrap()[0] = 7u8;
+
+        assert_eq!(list.get_raw(&entity).unwrap(), [7u8, 0, 0, 0].as_slice());
+        assert!(list.is_changed(&entity, 0));
+    }
+
+    #[test]
+    fn set_raw_rejects_a_length_that_does_not_match_the_stride()
+    {
+        let mut list = ComponentList::new::<u32>();
+        let entity = Entity::new(0, Uuid::new_v4());
+        list.add(&entity, &1u32, 0);
+
+        assert!(!list.set_raw(&entity, &[1u8, 2u8], 1));
+        // Untouched: the mismatched write was rejected before it reached the buffer.
+        assert_eq!(list.get_raw(&entity).unwrap(), [1u8, 0, 0, 0].as_slice());
+    }
+
+    #[test]
+    fn set_raw_overwrites_the_component_and_marks_it_changed()
+    {
+        let mut list = ComponentList::new::<u32>();
+        let entity = Entity::new(0, Uuid::new_v4());
+        list.add(&entity, &1u32, 0);
+
+        assert!(list.set_raw(&entity, &[2u8, 0, 0, 0], 3));
+
+        assert_eq!(list.get_raw(&entity).unwrap(), [2u8, 0, 0, 0].as_slice());
+        assert!(list.is_changed(&entity, 0));
+    }
+
+    #[test]
+    fn raw_accessors_see_nothing_for_an_entity_without_the_component()
+    {
+        let list = ComponentList::new::<u32>();
+        let entity = Entity::new(0, Uuid::new_v4());
+        assert!(list.get_raw(&entity).is_none());
+    }
 }
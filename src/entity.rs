@@ -126,6 +126,15 @@ impl EntityManager
         &self.entities[**entity] == entity && self.enabled[**entity]
     }
 
+    /// Returns every entity currently active, in index order.
+    pub fn active_entities(&self) -> Vec<Entity>
+    {
+        self.entities.iter().enumerate()
+            .filter(|&(i, _)| self.enabled.get(i))
+            .map(|(_, e)| e.clone())
+            .collect()
+    }
+
     /// Deletes an entity from the manager.
     pub fn delete_entity(&mut self, entity: &Entity)
     {
@@ -0,0 +1,113 @@
+
+//! Double-buffered, type-erased event storage for `World::send_event`/`World::events`.
+//!
+//! Events are plain `Copy+'static` values, just like components, so the same
+//! `Buffer`/`IntoBuffer` machinery backs their storage.
+
+use std::collections::HashMap;
+use std::intrinsics::TypeId;
+
+use buffer::Buffer;
+
+/// Uniquely identifies an event type, using the same scheme as `ComponentId`.
+pub type EventId = u64;
+
+/// One event type's buffers: the frame it was most recently swapped into, and the one
+/// currently being written to.
+struct EventBuffers
+{
+    previous: Buffer,
+    current: Buffer,
+}
+
+/// Stores every event type sent to a `World`. Each event stays readable for exactly one
+/// full frame after being sent, regardless of which order systems run in, because it's
+/// visible in `current` while being written and in `previous` for the whole frame after.
+#[doc(hidden)]
+pub struct Events
+{
+    buffers: HashMap<EventId, EventBuffers>,
+}
+
+impl Events
+{
+    pub fn new() -> Events
+    {
+        Events { buffers: HashMap::new() }
+    }
+
+    /// Pushes `event` into the current frame's buffer for `E`.
+    pub fn send<E:Copy+'static>(&mut self, event: E)
+    {
+        let id = TypeId::of::<E>().hash();
+        if !self.buffers.contains_key(&id)
+        {
+            self.buffers.insert(id, EventBuffers
+            {
+                previous: Buffer::new::<E>(),
+                current: Buffer::new::<E>(),
+            });
+        }
+        let buffers = self.buffers.get_mut(&id).unwrap();
+        let index = buffers.current.len();
+        buffers.current[index] = event;
+    }
+
+    /// Returns every `E` sent during the previous and current frame.
+    pub fn read<E:Copy+'static>(&self) -> Vec<E>
+    {
+        let id = TypeId::of::<E>().hash();
+        match self.buffers.get(&id)
+        {
+            Some(buffers) =>
+            {
+                let mut events = Vec::new();
+                for i in range(0, buffers.previous.len())
+                {
+                    events.push(buffers.previous[i]);
+                }
+                for i in range(0, buffers.current.len())
+                {
+                    events.push(buffers.current[i]);
+                }
+                events
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Swaps every event type's current frame into `previous` and starts a fresh,
+    /// empty current frame. Call once per world update.
+    pub fn swap(&mut self)
+    {
+        for buffers in self.buffers.values_mut()
+        {
+            let stride = buffers.current.stride();
+            buffers.previous = ::std::mem::replace(&mut buffers.current, Buffer::with_stride(stride));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::Events;
+
+    #[test]
+    fn event_is_readable_for_exactly_one_frame_after_the_one_it_was_sent_in()
+    {
+        let mut events = Events::new();
+        events.send(1u32);
+
+        // Visible the frame it was sent.
+        assert_eq!(events.read::<u32>(), vec![1u32]);
+
+        events.swap();
+        // Still visible the frame after.
+        assert_eq!(events.read::<u32>(), vec![1u32]);
+
+        events.swap();
+        // Gone the frame after that.
+        assert_eq!(events.read::<u32>(), Vec::new());
+    }
+}
@@ -41,16 +41,19 @@ extern crate log;
 pub use aspect::Aspect;
 pub use component::{Component, ComponentId};
 pub use entity::{Entity, EntityBuilder, EntityModifier};
-pub use manager::{Manager, MutableManager};
+pub use manager::{Manager, MutableManager, Observer, ObserverEvent};
 pub use system::{Passive, System};
 pub use world::{Components, EntityData, World, WorldBuilder};
 
 pub mod buffer;
 
+pub mod archetype;
 pub mod aspect;
 pub mod component;
 pub mod entity;
+pub mod events;
 pub mod manager;
+pub mod relations;
 pub mod system;
 pub mod world;
 
@@ -6,6 +6,7 @@
 use std::cell::RefCell;
 use std::rc::Rc;
 
+use component::ComponentId;
 use Entity;
 use World;
 
@@ -48,3 +49,35 @@ impl<T: MutableManager> MutableManager for Rc<RefCell<T>>
         self.borrow_mut().deactivated(e, w)
     }
 }
+
+/// The kind of change that triggered an `Observer`.
+#[deriving(Clone, PartialEq, Show)]
+pub enum ObserverEvent
+{
+    /// The component was added to the entity.
+    Added,
+    /// The component was already on the entity, and has changed.
+    Changed,
+    /// The component was removed from the entity.
+    Removed,
+}
+
+/// Watches a single component type on entities matching an `Aspect`, rather than every
+/// entity in the world like `Manager`/`MutableManager` do.
+///
+/// Registered through `WorldBuilder::add_observer`, an `Observer` is notified the
+/// moment a matching entity's watched component is added, changed, or removed, as part
+/// of the same `World` call that made the change (there is no polling or deferral).
+pub trait Observer: 'static
+{
+    /// Called when `component` changes on `entity` in a way described by `event`.
+    fn notify(&mut self, entity: &Entity, component: ComponentId, event: ObserverEvent, world: &World);
+}
+
+impl<T: Observer> Observer for Rc<RefCell<T>>
+{
+    fn notify(&mut self, entity: &Entity, component: ComponentId, event: ObserverEvent, world: &World)
+    {
+        self.borrow_mut().notify(entity, component, event, world)
+    }
+}
@@ -0,0 +1,199 @@
+
+//! Typed relationships between entities (eg: `ChildOf`, `Owns`), so callers don't have
+//! to store bare entity indices inside components.
+
+use std::collections::HashMap;
+
+use component::ComponentId;
+use Entity;
+
+/// A single edge. In the forward map the entity holding this is related to `target` via
+/// `relation`; in the reverse map `target` is the source entity related to the holder.
+#[deriving(Clone)]
+pub struct Relation
+{
+    /// Which kind of relationship this is.
+    pub relation: ComponentId,
+    /// The entity at the other end of the edge.
+    pub target: Entity,
+}
+
+/// Tracks typed relationships between entities in both directions, so either end can
+/// be queried without scanning every entity.
+#[doc(hidden)]
+pub struct Relations
+{
+    forward: HashMap<uint, Vec<Relation>>,
+    reverse: HashMap<uint, Vec<Relation>>,
+}
+
+impl Relations
+{
+    pub fn new() -> Relations
+    {
+        Relations
+        {
+            forward: HashMap::new(),
+            reverse: HashMap::new(),
+        }
+    }
+
+    /// Records that `source` is related to `target` via `relation`.
+    pub fn relate(&mut self, source: &Entity, relation: ComponentId, target: Entity)
+    {
+        if !self.forward.contains_key(&**source)
+        {
+            self.forward.insert(**source, Vec::new());
+        }
+        self.forward.get_mut(&**source).unwrap().push(Relation { relation: relation, target: target.clone() });
+
+        if !self.reverse.contains_key(&*target)
+        {
+            self.reverse.insert(*target, Vec::new());
+        }
+        self.reverse.get_mut(&*target).unwrap().push(Relation { relation: relation, target: source.clone() });
+    }
+
+    /// Removes a previously recorded relation, if it exists.
+    pub fn unrelate(&mut self, source: &Entity, relation: ComponentId, target: &Entity)
+    {
+        if let Some(edges) = self.forward.get_mut(&**source)
+        {
+            edges.retain(|edge| !(edge.relation == relation && edge.target == *target));
+        }
+        if let Some(edges) = self.reverse.get_mut(&**target)
+        {
+            edges.retain(|edge| !(edge.relation == relation && edge.target == *source));
+        }
+    }
+
+    /// Returns every entity `source` is related to via `relation`.
+    pub fn targets_of(&self, source: &Entity, relation: ComponentId) -> Vec<Entity>
+    {
+        self.forward.get(&**source).map_or(Vec::new(), |edges|
+            edges.iter().filter(|e| e.relation == relation).map(|e| e.target.clone()).collect())
+    }
+
+    /// Returns every entity related to `target` via `relation`.
+    pub fn sources_of(&self, target: &Entity, relation: ComponentId) -> Vec<Entity>
+    {
+        self.reverse.get(&**target).map_or(Vec::new(), |edges|
+            edges.iter().filter(|e| e.relation == relation).map(|e| e.target.clone()).collect())
+    }
+
+    /// Drops every relation touching `entity`: its own outgoing edges, and the dangling
+    /// edges left in other entities' lists where it was the target.
+    ///
+    /// Must be called before an entity's index is recycled, or a future entity created
+    /// with that index would inherit its relations.
+    pub fn remove_entity(&mut self, entity: &Entity)
+    {
+        if let Some(outgoing) = self.forward.remove(&**entity)
+        {
+            for edge in outgoing.iter()
+            {
+                if let Some(edges) = self.reverse.get_mut(&*edge.target)
+                {
+                    edges.retain(|e| !(e.relation == edge.relation && e.target == *entity));
+                }
+            }
+        }
+        if let Some(incoming) = self.reverse.remove(&**entity)
+        {
+            for edge in incoming.iter()
+            {
+                if let Some(edges) = self.forward.get_mut(&*edge.target)
+                {
+                    edges.retain(|e| !(e.relation == edge.relation && e.target == *entity));
+                }
+            }
+        }
+    }
+
+    /// Walks `relation` transitively and depth-first from `root`, following outgoing
+    /// edges only (eg: walking `ChildOf` from a child visits its parent, grandparent and
+    /// so on). Incoming edges are not followed, so descendants are not visited.
+    pub fn walk(&self, root: &Entity, relation: ComponentId) -> Vec<Entity>
+    {
+        let mut seen = Vec::new();
+        let mut order = Vec::new();
+        let mut stack = self.targets_of(root, relation);
+        while let Some(next) = stack.pop()
+        {
+            if seen.contains(&*next)
+            {
+                continue;
+            }
+            seen.push(*next);
+            order.push(next);
+            stack.push_all(self.targets_of(&next, relation).as_slice());
+        }
+        order
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::Relations;
+    use uuid::Uuid;
+    use Entity;
+
+    const CHILD_OF: u64 = 1u64;
+
+    #[test]
+    fn relate_is_queryable_from_both_directions()
+    {
+        let mut relations = Relations::new();
+        let parent = Entity::new(0, Uuid::new_v4());
+        let child = Entity::new(1, Uuid::new_v4());
+        relations.relate(&child, CHILD_OF, parent.clone());
+
+        assert_eq!(relations.targets_of(&child, CHILD_OF), vec![parent.clone()]);
+        assert_eq!(relations.sources_of(&parent, CHILD_OF), vec![child.clone()]);
+    }
+
+    #[test]
+    fn unrelate_drops_the_edge_in_both_directions()
+    {
+        let mut relations = Relations::new();
+        let parent = Entity::new(0, Uuid::new_v4());
+        let child = Entity::new(1, Uuid::new_v4());
+        relations.relate(&child, CHILD_OF, parent.clone());
+
+        relations.unrelate(&child, CHILD_OF, &parent);
+
+        assert!(relations.targets_of(&child, CHILD_OF).is_empty());
+        assert!(relations.sources_of(&parent, CHILD_OF).is_empty());
+    }
+
+    #[test]
+    fn remove_entity_drops_its_own_and_others_dangling_edges()
+    {
+        let mut relations = Relations::new();
+        let parent = Entity::new(0, Uuid::new_v4());
+        let child = Entity::new(1, Uuid::new_v4());
+        relations.relate(&child, CHILD_OF, parent.clone());
+
+        relations.remove_entity(&child);
+
+        // `child`'s own outgoing edge is gone, and so is the dangling reverse edge it
+        // left on `parent`.
+        assert!(relations.targets_of(&child, CHILD_OF).is_empty());
+        assert!(relations.sources_of(&parent, CHILD_OF).is_empty());
+    }
+
+    #[test]
+    fn walk_does_not_revisit_a_cycle()
+    {
+        let mut relations = Relations::new();
+        let a = Entity::new(0, Uuid::new_v4());
+        let b = Entity::new(1, Uuid::new_v4());
+        relations.relate(&a, CHILD_OF, b.clone());
+        relations.relate(&b, CHILD_OF, a.clone());
+
+        // Without the `seen` guard in `walk`, this would loop forever.
+        let order = relations.walk(&a, CHILD_OF);
+        assert_eq!(order.len(), 2u);
+    }
+}
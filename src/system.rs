@@ -0,0 +1,28 @@
+
+//! Traits for running logic over entities each world update.
+
+use Aspect;
+use Entity;
+use World;
+
+/// Runs logic on entities matching an `Aspect`, once per world update.
+pub trait System: 'static
+{
+    /// The `Aspect` used to decide which entities this system processes. Defaults to
+    /// matching no entities, for systems which only care about `World`-level state.
+    fn aspect(&self) -> Aspect
+    {
+        Aspect::never()
+    }
+
+    /// Called once per update for every entity matching `aspect`.
+    fn process(&mut self, &Entity, &World);
+}
+
+/// A system that doesn't process individual entities, but still wants to run once per
+/// world update (eg: rendering, input, timing).
+pub trait Passive: 'static
+{
+    /// Called once per world update.
+    fn process(&mut self, &World);
+}
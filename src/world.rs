@@ -0,0 +1,777 @@
+
+//! Organises entities, components, systems, and managers into a single simulation.
+
+use std::collections::HashMap;
+
+use archetype::Archetypes;
+use component::{Component, ComponentId, ComponentList};
+use entity::{Entity, EntityBuilder, EntityManager, EntityModifier};
+use events::Events;
+use manager::{Manager, MutableManager, Observer, ObserverEvent};
+use relations::Relations;
+use system::{Passive, System};
+use Aspect;
+
+/// Returns the `ComponentId` of `T`.
+fn id_of<T:Component>() -> ComponentId
+{
+    component_id!(T)
+}
+
+/// Typed access to a `World`'s components.
+///
+/// Passed to `EntityBuilder`/`EntityModifier` callbacks and used by `Aspect::check` so
+/// that neither has to know about the rest of a `World`.
+pub struct Components<'a>
+{
+    world: &'a mut World,
+}
+
+impl<'a> Components<'a>
+{
+    #[doc(hidden)]
+    pub fn new(world: &'a mut World) -> Components<'a>
+    {
+        Components { world: world }
+    }
+
+    /// Adds a component to `entity`, returning false if it already had one.
+    pub fn add<T:Component>(&mut self, entity: &Entity, component: T) -> bool
+    {
+        self.world.add(entity, component)
+    }
+
+    /// Overwrites the component already on `entity`, returning false if it had none.
+    pub fn set<T:Component>(&mut self, entity: &Entity, component: T) -> bool
+    {
+        self.world.set(entity, component)
+    }
+
+    /// Returns a copy of `entity`'s component, if it has one.
+    pub fn get<T:Component>(&self, entity: &Entity) -> Option<T>
+    {
+        self.world.components.get(&id_of::<T>()).and_then(|list| list.get(entity))
+    }
+
+    /// Borrows `entity`'s component, if it has one.
+    pub fn borrow<T:Component>(&self, entity: &Entity) -> Option<&T>
+    {
+        self.world.components.get(&id_of::<T>()).and_then(|list| list.borrow(entity))
+    }
+
+    /// Mutably borrows `entity`'s component, if it has one.
+    pub fn borrow_mut<T:Component>(&mut self, entity: &Entity) -> Option<&mut T>
+    {
+        self.world.borrow_mut(entity)
+    }
+
+    /// Removes `entity`'s component, returning false if it had none.
+    pub fn remove<T:Component>(&mut self, entity: &Entity) -> bool
+    {
+        self.world.remove::<T>(entity)
+    }
+
+    /// Returns true if `entity` has a component with the given `ComponentId`.
+    pub fn has(&self, id: ComponentId, entity: &Entity) -> bool
+    {
+        self.world.components.get(&id).map_or(false, |list| list.has(entity))
+    }
+
+    /// Returns true if the component identified by `id` was added to `entity` after `last_run`.
+    pub fn is_added(&self, id: ComponentId, entity: &Entity, last_run: u32) -> bool
+    {
+        self.world.components.get(&id).map_or(false, |list| list.is_added(entity, last_run))
+    }
+
+    /// Returns true if the component identified by `id` changed on `entity` after `last_run`.
+    pub fn is_changed(&self, id: ComponentId, entity: &Entity, last_run: u32) -> bool
+    {
+        self.world.components.get(&id).map_or(false, |list| list.is_changed(entity, last_run))
+    }
+
+    /// Returns the raw bytes of `entity`'s component identified by `id`, without
+    /// knowing its Rust type. For scripting/modding layers that only have a `ComponentId`.
+    pub fn borrow_raw(&self, id: ComponentId, entity: &Entity) -> Option<&[u8]>
+    {
+        self.world.components.get(&id).and_then(|list| list.get_raw(entity))
+    }
+
+    /// Records that `source` is related to `target` via the relation `R`.
+    pub fn relate<R:Component>(&mut self, source: &Entity, target: Entity)
+    {
+        self.world.relate::<R>(source, target);
+    }
+
+    /// Removes a previously recorded `R` relation between `source` and `target`.
+    pub fn unrelate<R:Component>(&mut self, source: &Entity, target: &Entity)
+    {
+        self.world.unrelate::<R>(source, target);
+    }
+
+    /// Returns every entity `source` is related to via `R`.
+    pub fn targets_of<R:Component>(&self, source: &Entity) -> Vec<Entity>
+    {
+        self.world.targets_of::<R>(source)
+    }
+
+    /// Returns every entity related to `target` via `R`.
+    pub fn sources_of<R:Component>(&self, target: &Entity) -> Vec<Entity>
+    {
+        self.world.sources_of::<R>(target)
+    }
+}
+
+/// A convenient handle to a single entity and the world it belongs to.
+pub struct EntityData<'a>
+{
+    /// The entity this handle refers to.
+    pub entity: Entity,
+    world: &'a World,
+}
+
+impl<'a> EntityData<'a>
+{
+    #[doc(hidden)]
+    pub fn new(entity: Entity, world: &'a World) -> EntityData<'a>
+    {
+        EntityData { entity: entity, world: world }
+    }
+
+    /// Borrows this entity's component, if it has one.
+    pub fn borrow<T:Component>(&self) -> Option<&T>
+    {
+        self.world.components.get(&id_of::<T>()).and_then(|list| list.borrow(&self.entity))
+    }
+}
+
+/// A component-and-`Aspect`-filtered `Observer` registration.
+struct ObserverEntry
+{
+    component: ComponentId,
+    aspect: Aspect,
+    observer: Box<Observer>,
+}
+
+/// Organises entities, components, systems, and managers together, and runs everything
+/// in the correct order.
+pub struct World
+{
+    entities: EntityManager,
+    components: HashMap<ComponentId, ComponentList>,
+    managers: Vec<Box<Manager>>,
+    mut_managers: Vec<Box<MutableManager>>,
+    observers: Vec<ObserverEntry>,
+    systems: Vec<(Box<System>, u32)>,
+    passive: Vec<Box<Passive>>,
+    tick: u32,
+    archetypes: Archetypes,
+    relations: Relations,
+    hierarchical_relations: Vec<ComponentId>,
+    events: Events,
+}
+
+impl World
+{
+    /// The archetype-grouped view of this world's components, for `System`s that want
+    /// to iterate a multi-component `Aspect` as a packed row scan instead of testing
+    /// every entity (see `archetype::Archetypes::matching`).
+    pub fn archetypes(&self) -> &Archetypes
+    {
+        &self.archetypes
+    }
+
+    /// Returns every component id `entity` currently carries.
+    fn mask_of(&self, entity: &Entity) -> Vec<ComponentId>
+    {
+        self.components.iter()
+            .filter(|&(_, list)| list.has(entity))
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    /// Copies `entity`'s current `id` bytes from its `ComponentList` (the authoritative
+    /// copy) into its archetype row, if it has one.
+    ///
+    /// `Archetypes::set_components` already rebuilds a row from `ComponentList` in full
+    /// whenever an entity's archetype membership changes; this handles the case where a
+    /// component's value changes in place, without the entity moving archetypes at all.
+    fn sync_archetype_row(&mut self, entity: &Entity, id: ComponentId)
+    {
+        let bytes = self.components.get(&id).and_then(|list| list.get_raw(entity)).map(|b| b.to_vec());
+        if let Some(bytes) = bytes
+        {
+            self.archetypes.sync_row(entity, id, bytes.as_slice());
+        }
+    }
+
+    /// Returns the world's current tick, which stamps every component added or changed
+    /// now. It starts at 1 and is bumped after each `System` runs in `update`.
+    ///
+    /// `System`s compare this against the tick they last ran at to find entities whose
+    /// components were added/changed since, via `Aspect::added`/`Aspect::changed`.
+    pub fn tick(&self) -> u32
+    {
+        self.tick
+    }
+
+    /// Adds a component to `entity`, returning false if it already had one.
+    pub fn add<T:Component>(&mut self, entity: &Entity, component: T) -> bool
+    {
+        let id = id_of::<T>();
+        let had = self.components.get(&id).map_or(false, |list| list.has(entity));
+        let tick = self.tick;
+        let ok = match self.components.get_mut(&id)
+        {
+            Some(list) => list.add(entity, &component, tick),
+            None => false,
+        };
+        if ok
+        {
+            let event = if had { ObserverEvent::Changed } else { ObserverEvent::Added };
+            self.dispatch_observers(entity, id, event);
+            if had
+            {
+                // Mask unchanged, so `set_components` won't run below; push the new
+                // value into the existing row directly.
+                self.sync_archetype_row(entity, id);
+            }
+            else
+            {
+                // A newly added component changes the entity's mask, so its row is
+                // rebuilt from scratch, pulling every component's current bytes
+                // (including the one just added above) straight out of `ComponentList`.
+                let mask = self.mask_of(entity);
+                self.archetypes.set_components(entity, mask, &self.components);
+            }
+        }
+        ok
+    }
+
+    /// Overwrites the component already on `entity`, returning false if it had none.
+    pub fn set<T:Component>(&mut self, entity: &Entity, component: T) -> bool
+    {
+        let id = id_of::<T>();
+        let tick = self.tick;
+        let ok = match self.components.get_mut(&id)
+        {
+            Some(list) => list.set(entity, &component, tick),
+            None => false,
+        };
+        if ok
+        {
+            self.dispatch_observers(entity, id, ObserverEvent::Changed);
+            self.sync_archetype_row(entity, id);
+        }
+        ok
+    }
+
+    /// Mutably borrows `entity`'s component, if it has one.
+    ///
+    /// Because the borrow may be used to mutate the component after this call returns,
+    /// it is marked as changed at the current tick and any observers watching it are
+    /// notified immediately, rather than on next access. For the same reason, there's no
+    /// hook here to re-sync the archetype row once the caller is done mutating through
+    /// the reference, so the row is synced with the bytes as they stand right now rather
+    /// than left pointing at a dropped entity: the entity's archetype membership is
+    /// unaffected, but this component's row may lag one mutation behind until the next
+    /// `set` or `borrow_mut` of this component, or an `add` or `remove` that changes the
+    /// entity's component set, rewrites it from `ComponentList`.
+    pub fn borrow_mut<T:Component>(&mut self, entity: &Entity) -> Option<&mut T>
+    {
+        let id = id_of::<T>();
+        let has = self.components.get(&id).map_or(false, |list| list.has(entity));
+        if has
+        {
+            self.dispatch_observers(entity, id, ObserverEvent::Changed);
+            self.sync_archetype_row(entity, id);
+        }
+        let tick = self.tick;
+        self.components.get_mut(&id).and_then(|list| list.borrow_mut(entity, tick))
+    }
+
+    /// Removes `entity`'s component, returning false if it had none.
+    pub fn remove<T:Component>(&mut self, entity: &Entity) -> bool
+    {
+        let id = id_of::<T>();
+        let ok = match self.components.get_mut(&id)
+        {
+            Some(list) => list.rm(entity),
+            None => false,
+        };
+        if ok
+        {
+            self.dispatch_observers(entity, id, ObserverEvent::Removed);
+            let mask = self.mask_of(entity);
+            self.archetypes.set_components(entity, mask, &self.components);
+        }
+        ok
+    }
+
+    /// Records that `source` is related to `target` via the relation `R` (eg: `ChildOf`).
+    pub fn relate<R:Component>(&mut self, source: &Entity, target: Entity)
+    {
+        self.relations.relate(source, id_of::<R>(), target);
+    }
+
+    /// Removes a previously recorded `R` relation between `source` and `target`.
+    pub fn unrelate<R:Component>(&mut self, source: &Entity, target: &Entity)
+    {
+        self.relations.unrelate(source, id_of::<R>(), target);
+    }
+
+    /// Returns every entity `source` is related to via `R`.
+    pub fn targets_of<R:Component>(&self, source: &Entity) -> Vec<Entity>
+    {
+        self.relations.targets_of(source, id_of::<R>())
+    }
+
+    /// Returns every entity related to `target` via `R`.
+    pub fn sources_of<R:Component>(&self, target: &Entity) -> Vec<Entity>
+    {
+        self.relations.sources_of(target, id_of::<R>())
+    }
+
+    /// Walks the `R` relation transitively and depth-first from `root`.
+    pub fn walk_relation<R:Component>(&self, root: &Entity) -> Vec<Entity>
+    {
+        self.relations.walk(root, id_of::<R>())
+    }
+
+    /// Emits an event for systems to pick up with `events` this frame and next.
+    ///
+    /// Complements the synchronous `Manager` callbacks with a decoupled queue. It takes
+    /// `&mut self`, so it is called from outside `update`: `System`s and `Passive`s get
+    /// only `&World`, and can read events through `events` but cannot send them.
+    pub fn send_event<E:Copy+'static>(&mut self, event: E)
+    {
+        self.events.send(event);
+    }
+
+    /// Returns every `E` sent during the previous and current frame's updates.
+    pub fn events<E:Copy+'static>(&self) -> Vec<E>
+    {
+        self.events.read()
+    }
+
+    /// Notifies every registered `Observer` watching component `id` whose `Aspect` also
+    /// matches `entity`, about a change described by `event`.
+    fn dispatch_observers(&mut self, entity: &Entity, id: ComponentId, event: ObserverEvent)
+    {
+        for i in range(0, self.observers.len())
+        {
+            if self.observers[i].component != id
+            {
+                continue;
+            }
+            let aspect = self.observers[i].aspect.clone();
+            let matches =
+            {
+                let components = Components::new(self);
+                aspect.check(entity, &components)
+            };
+            if matches
+            {
+                let entry = &mut self.observers[mut][i];
+                entry.observer.notify(entity, id, event, self);
+            }
+        }
+    }
+
+    /// Creates a new entity, running `builder` against it before activating it.
+    pub fn create_entity<B:EntityBuilder>(&mut self, mut builder: B) -> Entity
+    {
+        let entity = self.entities.create_entity();
+        {
+            let mut components = Components::new(self);
+            builder.build(&mut components, entity);
+        }
+        for manager in self.managers.iter()
+        {
+            manager.activated(&entity, self);
+        }
+        for manager in self.mut_managers.iter_mut()
+        {
+            manager.activated(&entity, self);
+        }
+        entity
+    }
+
+    /// Runs `modifier` against `entity`, then notifies managers it was reactivated.
+    pub fn modify_entity<M:EntityModifier>(&mut self, entity: Entity, mut modifier: M)
+    {
+        {
+            let mut components = Components::new(self);
+            modifier.modify(&mut components, entity);
+        }
+        for manager in self.managers.iter()
+        {
+            manager.reactivated(&entity, self);
+        }
+        for manager in self.mut_managers.iter_mut()
+        {
+            manager.reactivated(&entity, self);
+        }
+    }
+
+    /// Removes an entity from the world, notifying managers it was deactivated.
+    ///
+    /// A no-op if `entity` was already removed: the cascade below can otherwise reach the
+    /// same entity twice, via a relation cycle or two hierarchical relations converging on
+    /// one child, and recycling its index twice would hand it out to two live entities.
+    pub fn remove_entity(&mut self, entity: &Entity)
+    {
+        if !self.entities.is_valid(entity)
+        {
+            return;
+        }
+
+        for manager in self.managers.iter()
+        {
+            manager.deactivated(entity, self);
+        }
+        for manager in self.mut_managers.iter_mut()
+        {
+            manager.deactivated(entity, self);
+        }
+
+        // Invalidate the entity before cascading, so a cycle that leads back to it stops
+        // at the `is_valid` check above. Nothing below creates an entity, so the index
+        // returned to the pool here can't be reused before its edges and row are dropped.
+        self.entities.delete_entity(entity);
+
+        // Cascade-delete children along any hierarchical relation (eg: ChildOf) before
+        // dropping this entity's own edges.
+        for i in range(0, self.hierarchical_relations.len())
+        {
+            let relation = self.hierarchical_relations[i];
+            let children = self.relations.sources_of(entity, relation);
+            for child in children.iter()
+            {
+                self.remove_entity(child);
+            }
+        }
+        self.relations.remove_entity(entity);
+
+        // Drop archetype storage, so a future entity created with the same index
+        // doesn't inherit this one's row.
+        self.archetypes.remove_entity(entity);
+    }
+
+    /// Runs every registered `System` and `Passive` once.
+    ///
+    /// Each `System` runs at the world's current tick, records it as its last run, and
+    /// the tick is bumped after it. A change made by another system, or between updates,
+    /// therefore carries a tick newer than the system's last run and passes its
+    /// `added`/`changed` `Aspect` filters on its next run; the system's own changes don't.
+    pub fn update(&mut self)
+    {
+        let tick = self.tick;
+        for list in self.components.values_mut()
+        {
+            list.clamp_ticks(tick);
+        }
+
+        for i in range(0, self.systems.len())
+        {
+            let last_run = self.systems[i].1;
+            let aspect = self.systems[i].0.aspect();
+
+            // `all` is the only filter archetype storage can resolve directly; narrow to
+            // the matching archetypes' entities first, then fall back to testing every
+            // active entity only when the system has no `all` requirement at all.
+            let candidates: Vec<Entity> = {
+                let required = aspect.required_ids();
+                if required.len() > 0
+                {
+                    self.archetypes.matching(required).iter()
+                        .flat_map(|archetype| archetype.entities().iter())
+                        .map(|entity| entity.clone())
+                        .collect()
+                }
+                else
+                {
+                    self.entities.active_entities()
+                }
+            };
+
+            for entity in candidates.iter()
+            {
+                let matches =
+                {
+                    let components = Components::new(self);
+                    aspect.check_since(entity, &components, last_run)
+                };
+                if matches
+                {
+                    self.systems[mut][i].0.process(entity, self);
+                }
+            }
+            self.systems[mut][i].1 = self.tick;
+            self.tick = self.tick.wrapping_add(1);
+        }
+
+        for passive in self.passive.iter_mut()
+        {
+            passive.process(self);
+        }
+
+        // Events sent during this update stay readable for one more update, then go.
+        self.events.swap();
+    }
+}
+
+/// Builds a `World` up from its components, systems, and managers.
+pub struct WorldBuilder
+{
+    world: World,
+}
+
+impl WorldBuilder
+{
+    /// Returns a new, empty `WorldBuilder`.
+    pub fn new() -> WorldBuilder
+    {
+        WorldBuilder
+        {
+            world: World
+            {
+                entities: EntityManager::new(),
+                components: HashMap::new(),
+                managers: Vec::new(),
+                mut_managers: Vec::new(),
+                observers: Vec::new(),
+                systems: Vec::new(),
+                passive: Vec::new(),
+                // Above every system's initial last run of 0, so components added
+                // before the first update count as added.
+                tick: 1,
+                archetypes: Archetypes::new(),
+                relations: Relations::new(),
+                hierarchical_relations: Vec::new(),
+                events: Events::new(),
+            },
+        }
+    }
+
+    /// Registers a new component type, so entities may carry it.
+    pub fn register_component<T:Component>(&mut self) -> &mut WorldBuilder
+    {
+        let id = id_of::<T>();
+        let list = ComponentList::new::<T>();
+        self.world.archetypes.register_component(id, list.stride());
+        self.world.components.insert(id, list);
+        self
+    }
+
+    /// Registers a `System` to run every world update.
+    pub fn register_system(&mut self, system: Box<System>) -> &mut WorldBuilder
+    {
+        self.world.systems.push((system, 0));
+        self
+    }
+
+    /// Registers a `Passive` system to run every world update.
+    pub fn register_passive(&mut self, system: Box<Passive>) -> &mut WorldBuilder
+    {
+        self.world.passive.push(system);
+        self
+    }
+
+    /// Registers a `Manager` to observe entity activation/deactivation.
+    pub fn register_manager(&mut self, manager: Box<Manager>) -> &mut WorldBuilder
+    {
+        self.world.managers.push(manager);
+        self
+    }
+
+    /// Registers a `MutableManager` to observe entity activation/deactivation.
+    pub fn register_mut_manager(&mut self, manager: Box<MutableManager>) -> &mut WorldBuilder
+    {
+        self.world.mut_managers.push(manager);
+        self
+    }
+
+    /// Registers an `Observer` to watch `C` on entities matching `aspect`.
+    ///
+    /// `C` scopes the observer to one component type: a change to any other component
+    /// never reaches it, even on an entity the `aspect` would otherwise match.
+    pub fn add_observer<C:Component>(&mut self, aspect: Aspect, observer: Box<Observer>) -> &mut WorldBuilder
+    {
+        self.world.observers.push(ObserverEntry { component: id_of::<C>(), aspect: aspect, observer: observer });
+        self
+    }
+
+    /// Marks `R` as a hierarchical relation (eg: `ChildOf`), so deleting an entity
+    /// cascades to delete every entity related to it via `R`.
+    pub fn register_hierarchical_relation<R:Component>(&mut self) -> &mut WorldBuilder
+    {
+        self.world.hierarchical_relations.push(id_of::<R>());
+        self
+    }
+
+    /// Consumes this builder, returning the finished `World`.
+    pub fn build(self) -> World
+    {
+        self.world
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::{Components, EntityData, World, WorldBuilder};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use component::ComponentId;
+    use manager::{Manager, Observer, ObserverEvent};
+    use system::System;
+    use Aspect;
+    use Entity;
+
+    struct Counter(Rc<RefCell<uint>>);
+
+    impl Observer for Counter
+    {
+        fn notify(&mut self, _entity: &Entity, _component: ComponentId, _event: ObserverEvent, _world: &World)
+        {
+            let Counter(ref count) = *self;
+            *count.borrow_mut() += 1;
+        }
+    }
+
+    #[test]
+    fn observer_only_fires_for_its_own_component()
+    {
+        let mut builder = WorldBuilder::new();
+        builder.register_component::<u32>();
+        builder.register_component::<u64>();
+        let count = Rc::new(RefCell::new(0u));
+        builder.add_observer::<u32>(Aspect::nil(), box Counter(count.clone()) as Box<Observer>);
+        let mut world = builder.build();
+        let entity = world.create_entity(());
+
+        {
+            let mut components = Components::new(&mut world);
+            components.add(&entity, 1u64);
+        }
+        assert_eq!(*count.borrow(), 0u);
+
+        {
+            let mut components = Components::new(&mut world);
+            components.add(&entity, 1u32);
+        }
+        assert_eq!(*count.borrow(), 1u);
+    }
+
+    struct RecordingSystem(Rc<RefCell<Vec<u32>>>);
+
+    impl System for RecordingSystem
+    {
+        fn aspect(&self) -> Aspect
+        {
+            aspect!(u32 & u64)
+        }
+
+        fn process(&mut self, entity: &Entity, world: &World)
+        {
+            let RecordingSystem(ref seen) = *self;
+            let data = EntityData::new(entity.clone(), world);
+            if let Some(value) = data.borrow::<u32>()
+            {
+                seen.borrow_mut().push(*value);
+            }
+        }
+    }
+
+    #[test]
+    fn borrow_mut_does_not_evict_entity_from_archetype_matching()
+    {
+        let mut builder = WorldBuilder::new();
+        builder.register_component::<u32>();
+        builder.register_component::<u64>();
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        builder.register_system(box RecordingSystem(seen.clone()) as Box<System>);
+        let mut world = builder.build();
+
+        let entity = world.create_entity(());
+        {
+            let mut components = Components::new(&mut world);
+            components.add(&entity, 1u32);
+            components.add(&entity, 2u64);
+        }
+
+        // Mutate the u32 through `borrow_mut`, without any further `add`/`set`/`remove`
+        // call to rebuild the entity's archetype row afterwards.
+        {
+            let mut components = Components::new(&mut world);
+            if let Some(value) = components.borrow_mut::<u32>(&entity)
+            {
+                *value = 99u32;
+            }
+        }
+
+        // `aspect!(u32 & u64)` resolves its candidates exclusively through archetype
+        // matching (world.rs's `update`), so this only fires if `entity` is still in
+        // archetype storage. The value recorded is read from `ComponentList`, so it is
+        // the post-mutation one even though the archetype row still holds the old value.
+        world.update();
+        assert_eq!(*seen.borrow(), vec![99u32]);
+    }
+
+    struct DeactivationCounter(Rc<RefCell<uint>>);
+
+    impl Manager for DeactivationCounter
+    {
+        fn activated(&self, _entity: &Entity, _world: &World) {}
+        fn reactivated(&self, _entity: &Entity, _world: &World) {}
+
+        fn deactivated(&self, _entity: &Entity, _world: &World)
+        {
+            let DeactivationCounter(ref count) = *self;
+            *count.borrow_mut() += 1;
+        }
+    }
+
+    #[test]
+    fn remove_entity_cascades_to_children()
+    {
+        let mut builder = WorldBuilder::new();
+        builder.register_hierarchical_relation::<u8>();
+        let count = Rc::new(RefCell::new(0u));
+        builder.register_manager(box DeactivationCounter(count.clone()) as Box<Manager>);
+        let mut world = builder.build();
+
+        let parent = world.create_entity(());
+        let child = world.create_entity(());
+        world.relate::<u8>(&child, parent.clone());
+
+        world.remove_entity(&parent);
+
+        // Both the parent and its child were deactivated.
+        assert_eq!(*count.borrow(), 2u);
+    }
+
+    #[test]
+    fn remove_entity_does_not_revisit_a_child_reachable_twice()
+    {
+        let mut builder = WorldBuilder::new();
+        builder.register_hierarchical_relation::<u8>();
+        let count = Rc::new(RefCell::new(0u));
+        builder.register_manager(box DeactivationCounter(count.clone()) as Box<Manager>);
+        let mut world = builder.build();
+
+        let parent = world.create_entity(());
+        let child = world.create_entity(());
+        // Relate `child` to `parent` twice, so the cascade in `remove_entity` would
+        // reach `child` a second time (and try to recycle its index twice) if it
+        // weren't guarded against revisiting an already-removed entity.
+        world.relate::<u8>(&child, parent.clone());
+        world.relate::<u8>(&child, parent.clone());
+
+        world.remove_entity(&parent);
+
+        // `child` was only deactivated once, despite being reachable via two edges.
+        assert_eq!(*count.borrow(), 2u);
+    }
+}